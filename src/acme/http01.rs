@@ -0,0 +1,73 @@
+//! `http-01` challenge responder and CSR/expiry helpers for the ACME client.
+
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, SystemTime},
+};
+
+use p256::{ecdsa::SigningKey, pkcs8::EncodePrivateKey};
+use rand_core::OsRng;
+use rcgen::{CertificateParams, DistinguishedName};
+
+use crate::error::Error;
+
+/// Shared, concurrently-accessible map of challenge token -> key authorization,
+/// served by the web layer at `/.well-known/acme-challenge/{token}`.
+#[derive(Default)]
+pub struct ChallengeStore {
+    tokens: RwLock<HashMap<String, String>>,
+}
+
+impl ChallengeStore {
+    pub fn insert(&self, token: String, key_authorization: String) {
+        self.tokens.write().unwrap().insert(token, key_authorization);
+    }
+
+    pub fn get(&self, token: &str) -> Option<String> {
+        self.tokens.read().unwrap().get(token).cloned()
+    }
+}
+
+/// Generate a fresh ECDSA P-256 key and a DER-encoded CSR for `domains`.
+/// Returns `(pem_private_key, der_csr)`.
+pub fn generate_csr(domains: &[String]) -> Result<(String, Vec<u8>), Error> {
+    let signing_key = SigningKey::random(&mut OsRng);
+    let pkcs8_der = signing_key
+        .to_pkcs8_der()
+        .map_err(|e| Error::ParamError(e.to_string()))?;
+    let key_pair = rcgen::KeyPair::from_der(pkcs8_der.as_bytes())
+        .map_err(|e| Error::ParamError(e.to_string()))?;
+
+    let mut params = CertificateParams::new(domains.to_vec());
+    params.distinguished_name = DistinguishedName::new();
+    params.key_pair = Some(key_pair);
+
+    let cert = rcgen::Certificate::from_params(params).map_err(|e| Error::ParamError(e.to_string()))?;
+    let csr_der = cert
+        .serialize_request_der()
+        .map_err(|e| Error::ParamError(e.to_string()))?;
+    let private_key_pem = cert.serialize_private_key_pem();
+    Ok((private_key_pem, csr_der))
+}
+
+/// True when no certificate exists yet, it fails to parse, or it expires
+/// within `window`.
+pub fn certificate_needs_renewal(cert_path: &str, window: Duration) -> bool {
+    let Ok(pem) = std::fs::read_to_string(cert_path) else {
+        return true;
+    };
+    let Ok((_, pem_obj)) = x509_parser::pem::parse_x509_pem(pem.as_bytes()) else {
+        return true;
+    };
+    let Ok((_, cert)) = x509_parser::parse_x509_certificate(&pem_obj.contents) else {
+        return true;
+    };
+    let not_after = cert.validity().not_after.timestamp();
+    let deadline = SystemTime::now() + window;
+    let deadline_unix = deadline
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(i64::MAX);
+    not_after <= deadline_unix
+}
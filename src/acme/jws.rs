@@ -0,0 +1,111 @@
+//! Minimal JWS (RFC 7515) signing for the ACME protocol: ES256 over a
+//! protected header / payload pair, as required by RFC 8555 §6.2.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+pub fn base64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[derive(Serialize)]
+struct Jwk {
+    kty: &'static str,
+    crv: &'static str,
+    x: String,
+    y: String,
+}
+
+fn jwk(key: &SigningKey) -> Jwk {
+    let point = key.verifying_key().to_encoded_point(false);
+    Jwk {
+        kty: "EC",
+        crv: "P-256",
+        x: base64url(point.x().unwrap()),
+        y: base64url(point.y().unwrap()),
+    }
+}
+
+/// Canonical-JSON JWK thumbprint (RFC 7638) used by `key_authorization`.
+/// The `jwk` field of an unauthenticated ("new-account") JWS carries the
+/// full key from [`jwk`] instead.
+fn jwk_thumbprint(key: &SigningKey) -> String {
+    let k = jwk(key);
+    // RFC 7638 requires lexicographic key ordering with no whitespace.
+    let canonical = format!(
+        r#"{{"crv":"{}","kty":"{}","x":"{}","y":"{}"}}"#,
+        k.crv, k.kty, k.x, k.y
+    );
+    base64url(&Sha256::digest(canonical.as_bytes()))
+}
+
+/// `key_authorization = token + "." + base64url(SHA256(canonical-JSON JWK))`.
+pub fn key_authorization(account_key: &SigningKey, token: &str) -> Result<String, Error> {
+    Ok(format!("{}.{}", token, jwk_thumbprint(account_key)))
+}
+
+#[derive(Serialize)]
+struct ProtectedHeaderJwk<'a> {
+    alg: &'static str,
+    jwk: Jwk,
+    nonce: &'a str,
+    url: &'a str,
+}
+
+#[derive(Serialize)]
+struct ProtectedHeaderKid<'a> {
+    alg: &'static str,
+    kid: &'a str,
+    nonce: &'a str,
+    url: &'a str,
+}
+
+fn flattened_jws(protected: &str, payload: &str, signature: &Signature) -> Result<String, Error> {
+    let protected_b64 = base64url(protected.as_bytes());
+    let payload_b64 = base64url(payload.as_bytes());
+    let sig_b64 = base64url(&signature.to_bytes());
+    serde_json::to_string(&serde_json::json!({
+        "protected": protected_b64,
+        "payload": payload_b64,
+        "signature": sig_b64,
+    }))
+    .map_err(|e| e.into())
+}
+
+fn sign(account_key: &SigningKey, protected: &str, payload: &str) -> Result<String, Error> {
+    let signing_input = format!(
+        "{}.{}",
+        base64url(protected.as_bytes()),
+        base64url(payload.as_bytes())
+    );
+    let signature: Signature = account_key.sign(signing_input.as_bytes());
+    flattened_jws(protected, payload, &signature)
+}
+
+/// Sign a request for endpoints that require the full `jwk` (account
+/// creation), per RFC 8555 §6.2.
+pub fn sign_with_jwk(account_key: &SigningKey, url: &str, nonce: &str, payload: &str) -> Result<String, Error> {
+    let protected = serde_json::to_string(&ProtectedHeaderJwk {
+        alg: "ES256",
+        jwk: jwk(account_key),
+        nonce,
+        url,
+    })?;
+    sign(account_key, &protected, payload)
+}
+
+/// Sign a request for endpoints that use the account's `kid` once it is
+/// registered, per RFC 8555 §6.2.
+pub fn sign_with_kid(account_key: &SigningKey, kid: &str, url: &str, nonce: &str, payload: &str) -> Result<String, Error> {
+    let protected = serde_json::to_string(&ProtectedHeaderKid {
+        alg: "ES256",
+        kid,
+        nonce,
+        url,
+    })?;
+    sign(account_key, &protected, payload)
+}
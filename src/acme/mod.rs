@@ -0,0 +1,313 @@
+//! ACME v2 (RFC 8555) client used to provision and renew the TLS certificate
+//! declared in `[tls]` config, without relying on an external reverse proxy.
+//!
+//! Only the pieces this server needs are implemented: account creation,
+//! `http-01` domain validation, order finalization and certificate download.
+
+mod http01;
+mod jws;
+
+use std::{sync::Arc, time::Duration};
+
+use p256::ecdsa::SigningKey;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ConfigTls, error::Error};
+
+pub use http01::ChallengeStore;
+
+const LETS_ENCRYPT_DIRECTORY_URL: &str = "https://acme-v02.api.letsencrypt.org/directory";
+/// Re-check the certificate's expiry this often.
+const RENEWAL_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60 * 12);
+/// Renew once the certificate has less than this long left to live.
+const RENEWAL_WINDOW: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+#[derive(Deserialize)]
+struct Directory {
+    #[serde(rename = "newNonce")]
+    new_nonce: String,
+    #[serde(rename = "newAccount")]
+    new_account: String,
+    #[serde(rename = "newOrder")]
+    new_order: String,
+}
+
+#[derive(Serialize)]
+struct NewAccountPayload {
+    #[serde(rename = "termsOfServiceAgreed")]
+    terms_of_service_agreed: bool,
+    contact: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    status: String,
+}
+
+#[derive(Serialize)]
+struct NewOrderPayload {
+    identifiers: Vec<Identifier>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Identifier {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    status: String,
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authorization {
+    status: String,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct FinalizePayload {
+    csr: String,
+}
+
+/// Drives the ACME v2 protocol against a single CA directory on behalf of
+/// one account key.
+pub struct AcmeClient {
+    http: reqwest::Client,
+    directory_url: String,
+    account_key: SigningKey,
+    account_url: Option<String>,
+    directory: Option<Directory>,
+}
+
+impl AcmeClient {
+    pub fn new(directory_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            directory_url: directory_url.to_string(),
+            account_key: SigningKey::random(&mut OsRng),
+            account_url: None,
+            directory: None,
+        }
+    }
+
+    async fn directory(&mut self) -> Result<&Directory, Error> {
+        if self.directory.is_none() {
+            let directory = self
+                .http
+                .get(&self.directory_url)
+                .send()
+                .await
+                .map_err(|e| Error::ParamError(e.to_string()))?
+                .json::<Directory>()
+                .await
+                .map_err(|e| Error::ParamError(e.to_string()))?;
+            self.directory = Some(directory);
+        }
+        Ok(self.directory.as_ref().unwrap())
+    }
+
+    async fn fresh_nonce(&mut self) -> Result<String, Error> {
+        let url = self.directory().await?.new_nonce.clone();
+        let resp = self
+            .http
+            .head(&url)
+            .send()
+            .await
+            .map_err(|e| Error::ParamError(e.to_string()))?;
+        resp.headers()
+            .get("replay-nonce")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or_else(|| Error::ParamError("ACME server did not return a nonce".into()))
+    }
+
+    /// POST a JWS-signed request and return the response body alongside its
+    /// `Location` header, if any.
+    async fn post_jws(
+        &mut self,
+        url: &str,
+        payload: &str,
+        use_kid: bool,
+    ) -> Result<(reqwest::Response, Option<String>), Error> {
+        let nonce = self.fresh_nonce().await?;
+        let body = if use_kid {
+            let kid = self
+                .account_url
+                .clone()
+                .ok_or_else(|| Error::ParamError("ACME account not yet registered".into()))?;
+            jws::sign_with_kid(&self.account_key, &kid, url, &nonce, payload)?
+        } else {
+            jws::sign_with_jwk(&self.account_key, url, &nonce, payload)?
+        };
+
+        let resp = self
+            .http
+            .post(url)
+            .header("content-type", "application/jose+json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::ParamError(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            return Err(Error::ParamError(format!(
+                "ACME request to {} failed with status {}",
+                url,
+                resp.status()
+            )));
+        }
+
+        let location = resp
+            .headers()
+            .get("location")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+        Ok((resp, location))
+    }
+
+    async fn ensure_account(&mut self, contact_email: &str) -> Result<(), Error> {
+        if self.account_url.is_some() {
+            return Ok(());
+        }
+        let url = self.directory().await?.new_account.clone();
+        let payload = serde_json::to_string(&NewAccountPayload {
+            terms_of_service_agreed: true,
+            contact: vec![format!("mailto:{}", contact_email)],
+        })?;
+        let (resp, location) = self.post_jws(&url, &payload, false).await?;
+        let account: Account = resp.json().await.map_err(|e| Error::ParamError(e.to_string()))?;
+        if account.status != "valid" {
+            return Err(Error::ParamError(format!(
+                "ACME account status is {}",
+                account.status
+            )));
+        }
+        self.account_url = location;
+        Ok(())
+    }
+
+    /// Run the full issuance flow for `tls.domains`, persisting the resulting
+    /// certificate chain and private key at `tls.cert_path`/`tls.key_path`.
+    pub async fn issue_certificate(
+        &mut self,
+        tls: &ConfigTls,
+        challenges: &ChallengeStore,
+    ) -> Result<(), Error> {
+        self.ensure_account(&tls.acme_email).await?;
+
+        let new_order_url = self.directory().await?.new_order.clone();
+        let payload = serde_json::to_string(&NewOrderPayload {
+            identifiers: tls
+                .domains
+                .iter()
+                .map(|d| Identifier {
+                    kind: "dns".into(),
+                    value: d.clone(),
+                })
+                .collect(),
+        })?;
+        let (resp, order_url) = self.post_jws(&new_order_url, &payload, true).await?;
+        let order_url = order_url.ok_or_else(|| Error::ParamError("ACME order has no URL".into()))?;
+        let mut order: Order = resp.json().await.map_err(|e| Error::ParamError(e.to_string()))?;
+
+        for auth_url in order.authorizations.clone() {
+            self.complete_authorization(&auth_url, challenges).await?;
+        }
+
+        let (private_key, csr_der) = http01::generate_csr(&tls.domains)?;
+        let finalize_payload = serde_json::to_string(&FinalizePayload {
+            csr: jws::base64url(&csr_der),
+        })?;
+        self.post_jws(&order.finalize, &finalize_payload, true).await?;
+
+        let cert_url = loop {
+            let (resp, _) = self.post_jws(&order_url, "", true).await?;
+            order = resp.json().await.map_err(|e| Error::ParamError(e.to_string()))?;
+            match order.status.as_str() {
+                "valid" => break order.certificate.clone().ok_or_else(|| {
+                    Error::ParamError("ACME order is valid but has no certificate URL".into())
+                })?,
+                "invalid" => return Err(Error::ParamError("ACME order became invalid".into())),
+                _ => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+
+        let (resp, _) = self.post_jws(&cert_url, "", true).await?;
+        let chain_pem = resp.text().await.map_err(|e| Error::ParamError(e.to_string()))?;
+
+        std::fs::write(&tls.cert_path, chain_pem).map_err(|e| Error::ParamError(e.to_string()))?;
+        std::fs::write(&tls.key_path, private_key).map_err(|e| Error::ParamError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn complete_authorization(
+        &mut self,
+        auth_url: &str,
+        challenges: &ChallengeStore,
+    ) -> Result<(), Error> {
+        let (resp, _) = self.post_jws(auth_url, "", true).await?;
+        let auth: Authorization = resp.json().await.map_err(|e| Error::ParamError(e.to_string()))?;
+        if auth.status == "valid" {
+            return Ok(());
+        }
+
+        let challenge = auth
+            .challenges
+            .iter()
+            .find(|c| c.kind == "http-01")
+            .ok_or_else(|| Error::ParamError("no http-01 challenge offered".into()))?;
+
+        let key_authorization = jws::key_authorization(&self.account_key, &challenge.token)?;
+        challenges.insert(challenge.token.clone(), key_authorization);
+
+        self.post_jws(&challenge.url, "{}", true).await?;
+
+        for _ in 0..30 {
+            let (resp, _) = self.post_jws(auth_url, "", true).await?;
+            let auth: Authorization = resp.json().await.map_err(|e| Error::ParamError(e.to_string()))?;
+            match auth.status.as_str() {
+                "valid" => return Ok(()),
+                "invalid" => return Err(Error::ParamError("ACME authorization failed".into())),
+                _ => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        }
+        Err(Error::ParamError("ACME authorization timed out".into()))
+    }
+}
+
+/// Spawn the background renewal loop. Issues a certificate immediately if
+/// none exists yet, then re-checks on [`RENEWAL_CHECK_INTERVAL`].
+pub fn spawn_renewal_task(tls: ConfigTls, challenges: Arc<ChallengeStore>) {
+    tokio::spawn(async move {
+        let directory_url = if tls.acme_directory_url.is_empty() {
+            LETS_ENCRYPT_DIRECTORY_URL.to_string()
+        } else {
+            tls.acme_directory_url.clone()
+        };
+
+        loop {
+            if http01::certificate_needs_renewal(&tls.cert_path, RENEWAL_WINDOW) {
+                let mut client = AcmeClient::new(&directory_url);
+                if let Err(e) = client.issue_certificate(&tls, &challenges).await {
+                    log::error!("ACME certificate issuance failed: {}", e);
+                }
+            }
+            tokio::time::sleep(RENEWAL_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -27,6 +27,12 @@ pub struct KVConfig {
     pub db: ConfigDB,
     pub web: ConfigWeb,
     pub proof_service: ConfigProofService,
+    #[serde(default)]
+    pub tls: Option<ConfigTls>,
+    #[serde(default)]
+    pub consul: Option<ConfigConsul>,
+    #[serde(default)]
+    pub signer: Option<ConfigSigner>,
 }
 
 #[derive(Clone, Deserialize, Default)]
@@ -49,6 +55,50 @@ pub struct ConfigProofService {
     pub url: String,
 }
 
+/// ACME/Let's Encrypt TLS termination, see [`crate::acme`].
+#[derive(Clone, Deserialize, Default)]
+pub struct ConfigTls {
+    /// Domains to request a certificate for. All entries go into the CSR's
+    /// subject alternative names; the CSR's subject name is left empty.
+    pub domains: Vec<String>,
+    /// Contact email passed to the ACME account (recommended by the CA for
+    /// expiry notices).
+    pub acme_email: String,
+    /// ACME directory URL, defaults to Let's Encrypt production when empty.
+    #[serde(default)]
+    pub acme_directory_url: String,
+    /// Where the issued certificate/key PEM pair is persisted between renewals.
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+/// Consul service discovery, see [`crate::consul`]. When present,
+/// `proof_service.url` is resolved dynamically instead of used as-is, and
+/// the web listener registers itself as a Consul service.
+#[derive(Clone, Deserialize, Default)]
+pub struct ConfigConsul {
+    pub address: String,
+    /// Name of the proof service to resolve `proof_service.url` against.
+    pub service_name: String,
+    /// Name this server registers its own web listener under. Distinct from
+    /// `service_name` — they name two different services, and conflating
+    /// them makes this server resolve itself as its own proof service.
+    pub web_service_name: String,
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Optional server-side remote signer, see [`crate::signer`]. Lets thin
+/// clients that cannot hold a secp256k1 secret key locally opt a persona
+/// into custody by the server instead.
+#[derive(Clone, Deserialize, Default)]
+pub struct ConfigSigner {
+    /// Hex-encoded 32-byte key-encryption-key used to wrap vaulted secret keys.
+    pub kek: String,
+    /// Where the encrypted key vault is persisted.
+    pub vault_path: String,
+}
+
 #[derive(Clone, Deserialize)]
 pub enum ConfigCategory {
     File,
@@ -90,7 +140,57 @@ pub fn parse() -> Result<KVConfig, Error> {
 
 /// `AWS_SECRET_NAME` and `AWS_SECRET_REGION` is needed.
 pub fn from_aws_secret() -> Result<KVConfig, Error> {
-    todo!()
+    let secret_name = std::env::var("AWS_SECRET_NAME")
+        .map_err(|_| Error::ParamMissing("AWS_SECRET_NAME".to_string()))?;
+    let secret_region = std::env::var("AWS_SECRET_REGION")
+        .map_err(|_| Error::ParamMissing("AWS_SECRET_REGION".to_string()))?;
+
+    // `C` may first be dereferenced from inside the Lambda runtime's own
+    // tokio context, where spinning up a nested `Runtime` and calling
+    // `block_on` on it panics ("Cannot start a runtime from within a
+    // runtime"). Reuse the current runtime via `block_in_place` when one is
+    // already running, and only fall back to building a throwaway runtime
+    // when called from plain synchronous code.
+    let secret_value = match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| {
+            handle.block_on(fetch_aws_secret_value(&secret_name, &secret_region))
+        })?,
+        Err(_) => tokio::runtime::Runtime::new()
+            .map_err(|e| Error::ParamError(e.to_string()))?
+            .block_on(fetch_aws_secret_value(&secret_name, &secret_region))?,
+    };
+
+    let s = Config::builder()
+        // Secret payload is a JSON blob holding the same shape as the file-based config.
+        .add_source(config::File::from_str(&secret_value, config::FileFormat::Json))
+        // runtime-ENV-based config still layers on top.
+        .add_source(
+            config::Environment::with_prefix("KV")
+                .separator("__")
+                .ignore_empty(true),
+        )
+        .build()?;
+
+    s.try_deserialize().map_err(|e| e.into())
+}
+
+/// Fetch the raw JSON secret string for `secret_name` from AWS Secrets Manager.
+async fn fetch_aws_secret_value(secret_name: &str, secret_region: &str) -> Result<String, Error> {
+    let region = aws_sdk_secretsmanager::config::Region::new(secret_region.to_string());
+    let shared_config = aws_config::from_env().region(region).load().await;
+    let client = aws_sdk_secretsmanager::Client::new(&shared_config);
+
+    let output = client
+        .get_secret_value()
+        .secret_id(secret_name)
+        .send()
+        .await
+        .map_err(|e| Error::ParamError(e.to_string()))?;
+
+    output
+        .secret_string()
+        .map(|s| s.to_string())
+        .ok_or_else(|| Error::ParamError("AWS Secret has no string value".to_string()))
 }
 
 impl KVConfig {
@@ -0,0 +1,152 @@
+//! Consul-based service discovery for `proof_service.url`, and self
+//! registration of this server's web listener, so clustered deployments
+//! don't need a static, hand-maintained proof-service endpoint.
+//!
+//! Static config (`proof_service.url` as configured) remains the default
+//! whenever no `[consul]` section is present.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::config::{ConfigConsul, ConfigWeb, C};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    /// The live resolver when `[consul]` is configured, `None` otherwise —
+    /// in which case [`proof_service_url`] just returns the static config.
+    static ref RESOLVER: Option<Arc<ConsulProofServiceResolver>> = C.consul.clone().map(|consul| {
+        Arc::new(ConsulProofServiceResolver::new(consul, C.proof_service.url.clone()))
+    });
+}
+
+/// The URL `proof_client` should use for the proof service: Consul-resolved
+/// when `[consul]` is configured (refreshed in the background by
+/// [`spawn_refresh_task`]), otherwise the static `proof_service.url`.
+pub fn proof_service_url() -> String {
+    match RESOLVER.as_ref() {
+        Some(resolver) => resolver.url(),
+        None => C.proof_service.url.clone(),
+    }
+}
+
+/// Start the Consul background refresh loop and self-registration, a no-op
+/// when `[consul]` isn't configured. Called once at startup.
+pub async fn init() {
+    let Some(resolver) = RESOLVER.clone() else {
+        return;
+    };
+    spawn_refresh_task(resolver);
+    if let Err(e) = register_web_service(&C.consul.clone().unwrap(), &C.web).await {
+        log::error!("failed to register web listener with Consul: {}", e);
+    }
+}
+
+#[derive(Deserialize)]
+struct HealthEntry {
+    #[serde(rename = "Service")]
+    service: HealthService,
+}
+
+#[derive(Deserialize)]
+struct HealthService {
+    #[serde(rename = "Address")]
+    address: String,
+    #[serde(rename = "Port")]
+    port: u16,
+}
+
+/// Resolves `proof_service.url` against Consul, falling back to the last
+/// good value (or the static config) if Consul becomes unreachable.
+pub struct ConsulProofServiceResolver {
+    http: reqwest::Client,
+    consul: ConfigConsul,
+    cached_url: RwLock<String>,
+}
+
+impl ConsulProofServiceResolver {
+    pub fn new(consul: ConfigConsul, static_fallback_url: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            consul,
+            cached_url: RwLock::new(static_fallback_url),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.cached_url.read().unwrap().clone()
+    }
+
+    async fn query_healthy_instance(&self) -> Option<String> {
+        let url = format!(
+            "{}/v1/health/service/{}?passing=true",
+            self.consul.address.trim_end_matches('/'),
+            self.consul.service_name
+        );
+        let mut req = self.http.get(&url);
+        if let Some(token) = &self.consul.token {
+            req = req.header("X-Consul-Token", token);
+        }
+
+        let entries: Vec<HealthEntry> = req.send().await.ok()?.json().await.ok()?;
+        let entry = entries.first()?;
+        Some(format!("http://{}:{}", entry.service.address, entry.service.port))
+    }
+
+    /// Resolve once immediately, refreshing the cache if Consul is reachable
+    /// (keeping the previous value otherwise).
+    pub async fn refresh_once(&self) {
+        if let Some(url) = self.query_healthy_instance().await {
+            *self.cached_url.write().unwrap() = url;
+        } else {
+            log::warn!("Consul unreachable, keeping stale proof_service.url");
+        }
+    }
+}
+
+/// Spawn the background loop that keeps `resolver`'s cache warm.
+pub fn spawn_refresh_task(resolver: Arc<ConsulProofServiceResolver>) {
+    tokio::spawn(async move {
+        loop {
+            resolver.refresh_once().await;
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
+
+/// Register the web listener as a Consul service with a periodic HTTP
+/// health check, so the KV server itself is discoverable.
+pub async fn register_web_service(consul: &ConfigConsul, web: &ConfigWeb) -> Result<(), crate::error::Error> {
+    let registration = serde_json::json!({
+        "Name": consul.web_service_name,
+        "Address": web.listen,
+        "Port": web.port,
+        "Check": {
+            "HTTP": format!("http://{}:{}/health", web.listen, web.port),
+            "Interval": "10s",
+            "Timeout": "2s",
+        },
+    });
+
+    let url = format!("{}/v1/agent/service/register", consul.address.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+    let mut req = client.put(&url).json(&registration);
+    if let Some(token) = &consul.token {
+        req = req.header("X-Consul-Token", token);
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| crate::error::Error::ParamError(e.to_string()))?;
+    if !resp.status().is_success() {
+        return Err(crate::error::Error::ParamError(format!(
+            "Consul service registration failed with status {}",
+            resp.status()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use crate::{
+    acme::ChallengeStore,
+    controller::{Request, Response},
+    error::Error,
+};
+use http::StatusCode;
+
+/// Serves `/.well-known/acme-challenge/{token}` for the ACME `http-01`
+/// validation performed by [`crate::acme`]. Returns 404 for unknown tokens
+/// so the endpoint is harmless when no issuance is in flight.
+pub async fn controller(req: Request, challenges: Arc<ChallengeStore>) -> Result<Response, Error> {
+    let token = req
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .ok_or_else(|| Error::ParamMissing("token".to_string()))?;
+
+    match challenges.get(token) {
+        Some(key_authorization) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "text/plain")
+            .body(key_authorization)
+            .map_err(|e| Error::ParamError(e.to_string()))?),
+        None => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(String::new())
+            .map_err(|e| Error::ParamError(e.to_string()))?),
+    }
+}
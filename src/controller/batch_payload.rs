@@ -0,0 +1,254 @@
+use diesel::{insert_into, Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use http::StatusCode;
+use libsecp256k1::{Message, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    controller::{json_parse_body, json_response, Request, Response},
+    crypto::{http_signature, util::hex_public_key},
+    error::Error,
+    model::{establish_connection, kv_chains::{KVChain, NewKVChain}},
+    proof_client::can_set_kv,
+    schema::kv_chains::dsl::*,
+    util::{naive_now, vec_to_base64},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BatchItem {
+    pub platform: String,
+    pub identity: String,
+    pub patch: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BatchPayloadRequest {
+    pub items: Vec<BatchItem>,
+}
+
+/// Opaque pointer to the persona's chain head as observed while building the
+/// batch, echoed back on submission so the server can detect a concurrent
+/// writer and reject with a conflict instead of silently mis-chaining.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CausalityToken {
+    pub uuid: Option<String>,
+    pub created_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BatchPayloadResponse {
+    pub sign_payloads: Vec<String>,
+    pub causality_token: CausalityToken,
+}
+
+fn current_head(conn: &mut diesel::PgConnection, persona_bytes: &[u8]) -> Result<Option<KVChain>, Error> {
+    kv_chains
+        .filter(persona.eq(persona_bytes))
+        .order(created_at.desc())
+        .first::<KVChain>(conn)
+        .optional()
+        .map_err(|e| e.into())
+}
+
+fn verify_header(name: &str, req: &Request) -> Option<String> {
+    req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+/// `previous` in the wire-format `sign_payload` is the base64 of the parent
+/// entry's own `signature` column (see `payload.rs`'s `test_with_previous`),
+/// not its uuid. A batch item beyond the first has no persisted `signature`
+/// to point at yet — nothing has been signed or written at generation time —
+/// so it cannot carry a real one forward. Instead we chain in memory by
+/// hashing the previous item's own just-generated `sign_payload` JSON; this
+/// needs no DB round trip, is reproducible at submission time from the
+/// submitted strings alone, and `submit_controller` checks it the same way.
+fn chain_link(sign_payload_json: &str) -> String {
+    vec_to_base64(&Sha256::digest(sign_payload_json.as_bytes()))
+}
+
+/// Mirrors the shape `generate_signature_payload()` serializes, so a signed
+/// batch item can be turned back into a persistable `NewKVChain`. Every batch
+/// item, the first included, is built by hand as this type (see
+/// [`chain_link`]) instead of going through
+/// `NewKVChain::generate_signature_payload()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignPayloadDto {
+    pub uuid: ::uuid::Uuid,
+    pub persona: String,
+    pub platform: String,
+    pub identity: String,
+    pub patch: serde_json::Value,
+    pub previous: Option<String>,
+    pub created_at: i64,
+}
+
+/// Generates an ordered, in-memory-chained `sign_payload` per item so a
+/// persona can publish several patches atomically: item N's `previous` is the
+/// [`chain_link`] of item N-1's freshly computed payload, never a stale DB read.
+///
+/// `keyId` from the verified HTTP Signature is authoritative for which
+/// persona the batch is generated for, matching the single-set endpoint.
+pub async fn controller(req: Request) -> Result<Response, Error> {
+    let keypair = http_signature::verify(req.method().as_str(), req.uri().path(), req.body().as_bytes(), |name| {
+        verify_header(name, &req)
+    })?;
+
+    let params: BatchPayloadRequest = json_parse_body(&req)?;
+    if params.items.is_empty() {
+        return Err(Error::ParamError("items must not be empty".into()));
+    }
+
+    let persona_bytes = keypair.public_key.serialize().to_vec();
+    let persona_hex = hex_public_key(&keypair.public_key);
+
+    let mut conn = establish_connection();
+    let head = current_head(&mut conn, &persona_bytes)?;
+    let causality_token = CausalityToken {
+        uuid: head.as_ref().map(|h| h.uuid.to_string()),
+        created_at: head.as_ref().map(|h| h.created_at.timestamp()),
+    };
+
+    let mut sign_payloads = Vec::with_capacity(params.items.len());
+    // The first item's `previous` comes from the real, already-persisted
+    // chain head; every item after that chains off the previous item's
+    // freshly generated payload instead (see `chain_link`).
+    let mut previous: Option<String> = head.map(|h| vec_to_base64(&h.signature));
+
+    for item in &params.items {
+        can_set_kv(&keypair.public_key, &item.platform, &item.identity).await?;
+
+        let dto = SignPayloadDto {
+            uuid: ::uuid::Uuid::new_v4(),
+            persona: persona_hex.clone(),
+            platform: item.platform.clone(),
+            identity: item.identity.clone(),
+            patch: item.patch.clone(),
+            previous,
+            created_at: naive_now().timestamp(),
+        };
+        let sign_payload_json = serde_json::to_string(&dto)?;
+        previous = Some(chain_link(&sign_payload_json));
+        sign_payloads.push(sign_payload_json);
+    }
+
+    Ok(json_response(
+        StatusCode::OK,
+        &BatchPayloadResponse {
+            sign_payloads,
+            causality_token,
+        },
+    )?)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedBatchItem {
+    pub sign_payload: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BatchSubmitRequest {
+    pub causality_token: CausalityToken,
+    pub items: Vec<SignedBatchItem>,
+}
+
+/// Verify `item.signature` is a valid ECDSA secp256k1 signature by
+/// `persona_key` over `SHA256(item.sign_payload)`, the same scheme the
+/// single-set endpoint's payload signing uses.
+fn verify_item_signature(item: &SignedBatchItem, persona_key: &PublicKey) -> Result<(), Error> {
+    let signature_bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &item.signature)
+        .map_err(|e| Error::ParamError(e.to_string()))?;
+    let signature =
+        Signature::parse_standard_slice(&signature_bytes).map_err(|e| Error::ParamError(e.to_string()))?;
+    let digest = Sha256::digest(item.sign_payload.as_bytes());
+    let message = Message::parse_slice(&digest).map_err(|e| Error::ParamError(e.to_string()))?;
+
+    if !libsecp256k1::verify(&message, &signature, persona_key) {
+        return Err(Error::ParamError("invalid signature for batch item".into()));
+    }
+    Ok(())
+}
+
+/// Persists a batch produced by [`controller`], rejecting with a conflict if
+/// `causality_token` no longer matches the persona's current chain head
+/// (i.e. another writer advanced it since the batch was generated).
+///
+/// `keyId` from the verified HTTP Signature is authoritative for the
+/// persona, and every item's own signature is independently verified
+/// against it before anything is written — the request signature alone
+/// only proves who submitted the batch, not that each patch was approved.
+pub async fn submit_controller(req: Request) -> Result<Response, Error> {
+    let keypair = http_signature::verify(req.method().as_str(), req.uri().path(), req.body().as_bytes(), |name| {
+        verify_header(name, &req)
+    })?;
+    let persona_bytes = keypair.public_key.serialize().to_vec();
+
+    let params: BatchSubmitRequest = json_parse_body(&req)?;
+    if params.items.is_empty() {
+        return Err(Error::ParamError("items must not be empty".into()));
+    }
+
+    let mut conn = establish_connection();
+    let head = current_head(&mut conn, &persona_bytes)?;
+    let current_uuid = head.as_ref().map(|h| h.uuid.to_string());
+    if current_uuid != params.causality_token.uuid {
+        return Err(Error::ParamError(
+            "causality conflict: persona's chain advanced since this batch was generated".into(),
+        ));
+    }
+
+    // `decoded.previous` is purely a causality check — the base64 of the
+    // parent's real `signature` for the first item, or `chain_link` of the
+    // previous item's own submitted payload beyond that (see `chain_link`).
+    // The actual DB foreign key (`previous_id`) is tracked separately below,
+    // since it needs the parent's real uuid, which `previous` never carries.
+    let mut decoded_items = Vec::with_capacity(params.items.len());
+    let mut expected_previous = head.as_ref().map(|h| vec_to_base64(&h.signature));
+    let mut previous_id = head.map(|h| h.uuid);
+    for item in &params.items {
+        verify_item_signature(item, &keypair.public_key)?;
+
+        let decoded: SignPayloadDto = serde_json::from_str(&item.sign_payload)?;
+        if decoded.persona != hex_public_key(&keypair.public_key) {
+            return Err(Error::ParamError("sign_payload persona does not match the signing keyId".into()));
+        }
+        if decoded.previous != expected_previous {
+            return Err(Error::ParamError(
+                "causality conflict: batch item does not chain from the expected previous entry".into(),
+            ));
+        }
+        expected_previous = Some(chain_link(&item.sign_payload));
+        decoded_items.push((item.clone(), decoded, previous_id));
+        previous_id = Some(decoded_items.last().unwrap().1.uuid);
+    }
+
+    let persisted = conn
+        .transaction::<Vec<KVChain>, Error, _>(|conn| {
+            let mut persisted = Vec::with_capacity(decoded_items.len());
+            for (item, decoded, parent_id) in &decoded_items {
+                let signature = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &item.signature)
+                    .map_err(|e| Error::ParamError(e.to_string()))?;
+                persisted.push(
+                    insert_into(kv_chains)
+                        .values(&NewKVChain {
+                            uuid: decoded.uuid,
+                            persona: persona_bytes.clone(),
+                            platform: decoded.platform.clone(),
+                            identity: decoded.identity.clone(),
+                            patch: decoded.patch.clone(),
+                            previous_id: *parent_id,
+                            signature,
+                            signature_payload: item.sign_payload.clone(),
+                            created_at: naive_now(),
+                        })
+                        .get_result::<KVChain>(conn)?,
+                );
+            }
+            Ok(persisted)
+        })?;
+
+    Ok(json_response(
+        StatusCode::OK,
+        &persisted.iter().map(|c| c.uuid.to_string()).collect::<Vec<_>>(),
+    )?)
+}
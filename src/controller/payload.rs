@@ -1,10 +1,14 @@
+use std::sync::Arc;
+
 use crate::{
     controller::{json_parse_body, json_response, Request, Response},
-    crypto::secp256k1::Secp256k1KeyPair,
+    crypto::{http_signature, secp256k1::Secp256k1KeyPair},
     error::Error,
     model::{establish_connection, kv_chains::NewKVChain},
     proof_client::can_set_kv,
+    signer::{self, bearer_owner_token, SignerVault},
 };
+use diesel::{insert_into, RunQueryDsl};
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 
@@ -24,15 +28,31 @@ struct PayloadResponse {
     pub created_at: i64,
 }
 
-pub async fn controller(req: Request) -> Result<Response, Error> {
-    let params: PayloadRequest = json_parse_body(&req)?;
+/// Body for [`sign_and_submit_controller`]: unlike [`PayloadRequest`], the
+/// persona can't sign this request itself (that's the whole point), so
+/// `key_id` and the vault owner token (carried as a bearer
+/// `Authorization` header, see [`bearer_owner_token`]) stand in for the
+/// HTTP Signature used by the plain `controller`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignAndSubmitRequest {
+    pub key_id: String,
+    pub platform: String,
+    pub identity: String,
+    pub patch: serde_json::Value,
+}
 
-    let keypair = Secp256k1KeyPair::from_pubkey_hex(
-        &params
-            .avatar
-            .or(params.persona)
-            .ok_or_else(|| Error::ParamError("avatar not found".into()))?,
+pub async fn controller(req: Request) -> Result<Response, Error> {
+    // `keyId` from the verified HTTP Signature is authoritative; the
+    // avatar/persona field in the body is advisory only and no longer
+    // trusted on its own.
+    let keypair = http_signature::verify(
+        req.method().as_str(),
+        req.uri().path(),
+        req.body().as_bytes(),
+        |name| req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string),
     )?;
+
+    let params: PayloadRequest = json_parse_body(&req)?;
     can_set_kv(&keypair.public_key, &params.platform, &params.identity).await?;
     let mut conn = establish_connection();
     let mut new_kvchain = NewKVChain::for_persona(&mut conn, &keypair.public_key)?;
@@ -52,12 +72,57 @@ pub async fn controller(req: Request) -> Result<Response, Error> {
     )?)
 }
 
+/// Companion to [`controller`] for personas registered with the remote
+/// signer ([`crate::signer`]): signs the generated `sign_payload` in-process
+/// and persists the resulting chained, signature-verified `NewKVChain`
+/// immediately, so thin clients never need to sign locally.
+///
+/// Since these callers cannot produce an HTTP Signature with a key they
+/// don't hold, they instead prove they own `key_id` with the owner token
+/// returned at registration time (`Authorization: Bearer <token>`).
+pub async fn sign_and_submit_controller(req: Request, vault: Arc<SignerVault>) -> Result<Response, Error> {
+    use crate::schema::kv_chains::dsl::kv_chains;
+
+    let owner_token = bearer_owner_token(&req)?;
+    let params: SignAndSubmitRequest = json_parse_body(&req)?;
+    let keypair = Secp256k1KeyPair::from_pubkey_hex(&params.key_id)?;
+    vault.verify_owner_token(&params.key_id, &owner_token)?;
+
+    can_set_kv(&keypair.public_key, &params.platform, &params.identity).await?;
+    let mut conn = establish_connection();
+    let mut new_kvchain = NewKVChain::for_persona(&mut conn, &keypair.public_key)?;
+
+    new_kvchain.platform = params.platform;
+    new_kvchain.identity = params.identity;
+    new_kvchain.patch = params.patch;
+    let sign_payload = new_kvchain.generate_signature_payload()?;
+    let sign_payload_json = serde_json::to_string(&sign_payload)?;
+
+    new_kvchain.signature_payload = sign_payload_json.clone();
+    new_kvchain.signature = signer::sign_bytes(&vault, &params.key_id, &owner_token, &sign_payload_json)?;
+
+    let persisted = insert_into(kv_chains)
+        .values(&new_kvchain)
+        .get_result::<crate::model::kv_chains::KVChain>(&mut conn)?;
+
+    Ok(json_response(
+        StatusCode::OK,
+        &PayloadResponse {
+            sign_payload: sign_payload_json,
+            uuid: persisted.uuid.to_string(),
+            created_at: sign_payload.created_at,
+        },
+    )?)
+}
+
 #[cfg(test)]
 mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
     use diesel::{insert_into, PgConnection, RunQueryDsl};
     use fake::{Fake, Faker};
     use http::Method;
-    use libsecp256k1::PublicKey;
+    use libsecp256k1::{Message, PublicKey, SecretKey, Signature};
+    use sha2::{Digest as _, Sha256};
     use serde_json::json;
 
     use crate::{
@@ -69,6 +134,35 @@ mod tests {
 
     use super::*;
 
+    /// Build a POST request with a `Signature` header (draft-cavage style)
+    /// that `http_signature::verify` will accept.
+    fn signed_request(path: &str, body: String, secret_key: &SecretKey, key_id: String) -> Request {
+        let now = chrono::Utc::now().to_rfc2822();
+        let digest = format!("SHA-256={}", STANDARD.encode(Sha256::digest(body.as_bytes())));
+
+        let signing_string = format!(
+            "(request-target): post {}\ndigest: {}\ndate: {}",
+            path, digest, now
+        );
+        let message = Message::parse_slice(&Sha256::digest(signing_string.as_bytes())).unwrap();
+        let (signature, _) = libsecp256k1::sign(&message, secret_key);
+
+        let signature_header = format!(
+            r#"keyId="{}",algorithm="ecdsa-secp256k1-sha256",headers="(request-target) digest date",signature="{}""#,
+            key_id,
+            STANDARD.encode(signature.serialize())
+        );
+
+        ::http::Request::builder()
+            .method(Method::POST)
+            .uri(format!("http://localhost{}", path))
+            .header("digest", digest)
+            .header("date", now)
+            .header("signature", signature_header)
+            .body(body)
+            .unwrap()
+    }
+
     fn generate_data(conn: &mut PgConnection, persona_pubkey: &PublicKey) -> Result<KVChain, Error> {
         let new_uuid = ::uuid::Uuid::new_v4();
         let persona_bytes = persona_pubkey.serialize().to_vec();
@@ -94,7 +188,7 @@ mod tests {
     async fn test_success() {
         let Secp256k1KeyPair {
             public_key,
-            secret_key: _,
+            secret_key,
         } = Secp256k1KeyPair::generate();
 
         let req_body = PayloadRequest {
@@ -104,11 +198,12 @@ mod tests {
             identity: Faker.fake(),
             patch: json!({"test":"abc"}),
         };
-        let req: Request = ::http::Request::builder()
-            .method(Method::POST)
-            .uri(format!("http://localhost?test"))
-            .body(serde_json::to_string(&req_body).unwrap())
-            .unwrap();
+        let req: Request = signed_request(
+            "/",
+            serde_json::to_string(&req_body).unwrap(),
+            &secret_key,
+            hex_public_key(&public_key),
+        );
         let resp = controller(req).await.unwrap();
         let body: PayloadResponse = serde_json::from_str(resp.body()).unwrap();
         assert!(body.uuid.len() > 0);
@@ -125,7 +220,7 @@ mod tests {
         let mut conn = establish_connection();
         let Secp256k1KeyPair {
             public_key,
-            secret_key: _,
+            secret_key,
         } = Secp256k1KeyPair::generate();
         let old_kv_chain = generate_data(&mut conn, &public_key).unwrap();
 
@@ -136,14 +231,38 @@ mod tests {
             identity: Faker.fake(),
             patch: json!({"test":"abc"}),
         };
-        let req: Request = ::http::Request::builder()
-            .method(Method::POST)
-            .uri(format!("http://localhost?test"))
-            .body(serde_json::to_string(&req_body).unwrap())
-            .unwrap();
+        let req: Request = signed_request(
+            "/",
+            serde_json::to_string(&req_body).unwrap(),
+            &secret_key,
+            hex_public_key(&public_key),
+        );
         let resp = controller(req).await.unwrap();
         let body: PayloadResponse = serde_json::from_str(resp.body()).unwrap();
         let payload = body.sign_payload;
         assert!(payload.contains(&vec_to_base64(&old_kv_chain.signature)));
     }
+
+    #[tokio::test]
+    async fn test_rejects_unsigned_request() {
+        let Secp256k1KeyPair {
+            public_key,
+            secret_key: _,
+        } = Secp256k1KeyPair::generate();
+
+        let req_body = PayloadRequest {
+            persona: Some(compress_public_key(&public_key)),
+            avatar: None,
+            platform: "facebook".into(),
+            identity: Faker.fake(),
+            patch: json!({"test":"abc"}),
+        };
+        let req: Request = ::http::Request::builder()
+            .method(Method::POST)
+            .uri("http://localhost/")
+            .body(serde_json::to_string(&req_body).unwrap())
+            .unwrap();
+
+        assert!(controller(req).await.is_err());
+    }
 }
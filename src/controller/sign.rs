@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use crate::{
+    controller::{json_parse_body, json_response, Request, Response},
+    error::Error,
+    signer::{self, bearer_owner_token, SignRequest, SignResponse, SignerVault},
+};
+use http::StatusCode;
+
+/// `/sign`: signs an exact `sign_payload` with the vaulted secret key named
+/// by `key_id`, for clients that opted a persona into server-side custody.
+/// Requires the owner token returned at registration time — `key_id` alone
+/// (a public persona pubkey) is not proof of ownership.
+pub async fn controller(req: Request, vault: Arc<SignerVault>) -> Result<Response, Error> {
+    let owner_token = bearer_owner_token(&req)?;
+    let params: SignRequest = json_parse_body(&req)?;
+    let signature = signer::sign(&vault, &params.key_id, &owner_token, &params.sign_payload)?;
+
+    Ok(json_response(StatusCode::OK, &SignResponse { signature })?)
+}
@@ -0,0 +1,148 @@
+//! HTTP Signature authentication (draft-cavage / RFC 9421 style) for
+//! endpoints where the caller must prove control of the persona key that
+//! signs the request, not just name it in the body.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use libsecp256k1::{Message, Signature};
+use sha2::{Digest as _, Sha256};
+
+use super::secp256k1::Secp256k1KeyPair;
+use crate::error::Error;
+
+const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+pub struct SignatureParams {
+    pub key_id: String,
+    pub algorithm: String,
+    pub headers: Vec<String>,
+    pub signature: Vec<u8>,
+}
+
+/// Parse the `Signature` header's `key="value"` pairs, e.g.
+/// `keyId="...",algorithm="ecdsa-secp256k1-sha256",headers="(request-target) date digest",signature="..."`.
+fn parse_signature_header(header: &str) -> Result<SignatureParams, Error> {
+    let mut key_id = None;
+    let mut algorithm = None;
+    let mut headers = None;
+    let mut signature = None;
+
+    for field in header.split(',') {
+        let (name, value) = field
+            .split_once('=')
+            .ok_or_else(|| Error::ParamError("malformed Signature header".into()))?;
+        let value = value.trim().trim_matches('"');
+        match name.trim() {
+            "keyId" => key_id = Some(value.to_string()),
+            "algorithm" => algorithm = Some(value.to_string()),
+            "headers" => headers = Some(value.split(' ').map(str::to_string).collect()),
+            "signature" => {
+                signature = Some(
+                    STANDARD
+                        .decode(value)
+                        .map_err(|e| Error::ParamError(e.to_string()))?,
+                )
+            }
+            _ => {}
+        }
+    }
+
+    Ok(SignatureParams {
+        key_id: key_id.ok_or_else(|| Error::ParamError("Signature missing keyId".into()))?,
+        algorithm: algorithm.ok_or_else(|| Error::ParamError("Signature missing algorithm".into()))?,
+        headers: headers.ok_or_else(|| Error::ParamError("Signature missing headers".into()))?,
+        signature: signature.ok_or_else(|| Error::ParamError("Signature missing signature".into()))?,
+    })
+}
+
+/// Build the `(request-target)`-prefixed signing string from the ordered
+/// header list named in the `Signature` header.
+fn build_signing_string(
+    method: &str,
+    path: &str,
+    headers: &[String],
+    header_lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, Error> {
+    let mut lines = Vec::with_capacity(headers.len());
+    for name in headers {
+        if name == "(request-target)" {
+            lines.push(format!("(request-target): {} {}", method.to_lowercase(), path));
+        } else {
+            let value = header_lookup(name)
+                .ok_or_else(|| Error::ParamError(format!("missing signed header: {}", name)))?;
+            lines.push(format!("{}: {}", name, value));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Verify that `digest_header` (`SHA-256=<base64>`) matches the SHA-256 of
+/// `body`.
+fn verify_digest(digest_header: &str, body: &[u8]) -> Result<(), Error> {
+    let encoded = digest_header
+        .strip_prefix("SHA-256=")
+        .ok_or_else(|| Error::ParamError("Digest header must use SHA-256".into()))?;
+    let expected = STANDARD
+        .decode(encoded)
+        .map_err(|e| Error::ParamError(e.to_string()))?;
+    let actual = Sha256::digest(body);
+    if actual.as_slice() != expected.as_slice() {
+        return Err(Error::ParamError("Digest does not match request body".into()));
+    }
+    Ok(())
+}
+
+/// Verify `date_header` (HTTP-date) is within [`MAX_CLOCK_SKEW_SECS`] of now.
+fn verify_date(date_header: &str) -> Result<(), Error> {
+    let date = DateTime::parse_from_rfc2822(date_header)
+        .map_err(|e| Error::ParamError(e.to_string()))?
+        .with_timezone(&Utc);
+    let skew = (Utc::now() - date).num_seconds().abs();
+    if skew > MAX_CLOCK_SKEW_SECS {
+        return Err(Error::ParamError("Date header outside acceptable clock skew".into()));
+    }
+    Ok(())
+}
+
+/// Verify an HTTP Signature over `method`/`path`, returning the persona
+/// public key (`keyId`) proven to have signed the request.
+///
+/// `header_lookup` resolves a lower-cased header name to its value, and
+/// should include `digest` and `date` since both are required to be signed.
+pub fn verify(
+    method: &str,
+    path: &str,
+    body: &[u8],
+    header_lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Secp256k1KeyPair, Error> {
+    let signature_header = header_lookup("signature")
+        .ok_or_else(|| Error::ParamMissing("Signature".to_string()))?;
+    let params = parse_signature_header(&signature_header)?;
+
+    if !params.headers.iter().any(|h| h == "digest") {
+        return Err(Error::ParamError("Signature must cover Digest".into()));
+    }
+    if !params.headers.iter().any(|h| h == "date") {
+        return Err(Error::ParamError("Signature must cover Date".into()));
+    }
+
+    let digest_header =
+        header_lookup("digest").ok_or_else(|| Error::ParamMissing("Digest".to_string()))?;
+    verify_digest(&digest_header, body)?;
+
+    let date_header = header_lookup("date").ok_or_else(|| Error::ParamMissing("Date".to_string()))?;
+    verify_date(&date_header)?;
+
+    let signing_string = build_signing_string(method, path, &params.headers, header_lookup)?;
+    let keypair = Secp256k1KeyPair::from_pubkey_hex(&params.key_id)?;
+
+    let digest = Sha256::digest(signing_string.as_bytes());
+    let message = Message::parse_slice(&digest).map_err(|e| Error::ParamError(e.to_string()))?;
+    let signature =
+        Signature::parse_standard_slice(&params.signature).map_err(|e| Error::ParamError(e.to_string()))?;
+    if !libsecp256k1::verify(&message, &signature, &keypair.public_key) {
+        return Err(Error::ParamError("HTTP signature verification failed".into()));
+    }
+
+    Ok(keypair)
+}
@@ -0,0 +1,82 @@
+//! Optional remote-signer ("key custody") subsystem: stores secp256k1
+//! secret keys encrypted at rest, keyed by persona pubkey, and signs on
+//! behalf of personas that have opted in, so thin clients never have to
+//! hold a secret key themselves.
+//!
+//! Disabled unless `[signer]` is present in config.
+
+mod vault;
+
+use libsecp256k1::{Message, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{config::ConfigSigner, controller::Request, crypto::util::hex_public_key, error::Error};
+
+pub use vault::SignerVault;
+
+/// Extract the bearer owner token proving control of a vaulted `key_id` from
+/// `Authorization: Bearer <token>` — shared by every endpoint that
+/// authenticates via vault ownership instead of an HTTP Signature
+/// ([`crate::controller::sign`], [`crate::controller::payload::sign_and_submit_controller`]).
+pub fn bearer_owner_token(req: &Request) -> Result<String, Error> {
+    req.headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .ok_or_else(|| Error::ParamMissing("Authorization".to_string()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignRequest {
+    /// Persona public key (hex) identifying the vaulted secret key.
+    pub key_id: String,
+    /// The exact `sign_payload` JSON produced by `generate_signature_payload()`.
+    pub sign_payload: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignResponse {
+    pub signature: String,
+}
+
+/// Recompute the digest of `sign_payload` and sign it with the vaulted
+/// secret key registered for `key_id`, returning the raw ECDSA signature
+/// bytes. The decrypted key never leaves the vault's zeroizing closure.
+///
+/// `owner_token` must match the token returned by [`register`] for this
+/// `key_id` — since `key_id` is a public persona pubkey, it proves nothing
+/// on its own, and this is the only proof of ownership the vault accepts.
+pub fn sign_bytes(vault: &SignerVault, key_id: &str, owner_token: &str, sign_payload: &str) -> Result<Vec<u8>, Error> {
+    vault.verify_owner_token(key_id, owner_token)?;
+
+    let digest = Sha256::digest(sign_payload.as_bytes());
+    let message = Message::parse_slice(&digest).map_err(|e| Error::ParamError(e.to_string()))?;
+
+    vault.use_secret_key(key_id, |secret_key| {
+        libsecp256k1::sign(&message, secret_key).0.serialize().to_vec()
+    })
+}
+
+/// Same as [`sign_bytes`] but base64-encoded, for the `/sign` HTTP response.
+pub fn sign(vault: &SignerVault, key_id: &str, owner_token: &str, sign_payload: &str) -> Result<String, Error> {
+    let signature = sign_bytes(vault, key_id, owner_token, sign_payload)?;
+    Ok(base64::Engine::encode(
+        &base64::engine::general_purpose::STANDARD,
+        signature,
+    ))
+}
+
+/// Register `secret_key` in the vault under the hex of its persona pubkey,
+/// encrypting it at rest with the configured KEK. Returns `(key_id,
+/// owner_token)`; `owner_token` is generated fresh here and never
+/// recoverable afterwards, so the caller must save it to use `/sign` or the
+/// sign-and-submit path later.
+pub fn register(config: &ConfigSigner, vault: &SignerVault, secret_key: &SecretKey) -> Result<(String, String), Error> {
+    let public_key = libsecp256k1::PublicKey::from_secret_key(secret_key);
+    let key_id = hex_public_key(&public_key);
+    let owner_token = vault.insert(&key_id, secret_key)?;
+    vault.persist(&config.vault_path)?;
+    Ok((key_id, owner_token))
+}
@@ -0,0 +1,154 @@
+//! Encrypted, in-memory key store backing [`super::sign`]/[`super::register`].
+//! Secret keys are kept on the heap only as AES-256-GCM ciphertext; the
+//! plaintext key exists for the lifetime of a single callback and is
+//! zeroized as soon as that callback returns.
+
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use libsecp256k1::SecretKey;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::error::Error;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct VaultEntry {
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+    /// SHA-256 of the owner token handed back once at registration time.
+    /// Proves the `/sign` caller is the party that registered `key_id`,
+    /// since `key_id` itself (a persona pubkey) is public.
+    owner_token_hash: Vec<u8>,
+}
+
+/// Constant-time byte comparison, used to check owner tokens without
+/// leaking timing information about how much of the token matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// KEK-encrypted secp256k1 secret keys, keyed by persona pubkey hex.
+pub struct SignerVault {
+    cipher: Aes256Gcm,
+    entries: RwLock<HashMap<String, VaultEntry>>,
+}
+
+impl SignerVault {
+    /// `kek_hex` must decode to exactly 32 bytes.
+    pub fn new(kek_hex: &str) -> Result<Self, Error> {
+        let kek = hex::decode(kek_hex).map_err(|e| Error::ParamError(e.to_string()))?;
+        let cipher = Aes256Gcm::new_from_slice(&kek).map_err(|e| Error::ParamError(e.to_string()))?;
+        Ok(Self {
+            cipher,
+            entries: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn load(kek_hex: &str, vault_path: &str) -> Result<Self, Error> {
+        let vault = Self::new(kek_hex)?;
+        if let Ok(raw) = std::fs::read_to_string(vault_path) {
+            let entries: HashMap<String, VaultEntry> =
+                serde_json::from_str(&raw).map_err(|e| Error::ParamError(e.to_string()))?;
+            *vault.entries.write().unwrap() = entries;
+        }
+        Ok(vault)
+    }
+
+    /// Encrypts `secret_key` under `key_id` and returns a freshly generated
+    /// owner token; that token is the only proof of ownership `/sign` will
+    /// accept and is never stored or returned again after this call.
+    pub fn insert(&self, key_id: &str, secret_key: &SecretKey) -> Result<String, Error> {
+        let mut nonce_bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+
+        let mut plaintext = secret_key.serialize();
+        let ciphertext = self
+            .cipher
+            .encrypt(nonce, plaintext.as_ref())
+            .map_err(|e| Error::ParamError(e.to_string()))?;
+        plaintext.zeroize();
+
+        let mut owner_token = [0u8; 32];
+        OsRng.fill_bytes(&mut owner_token);
+        let owner_token_hash = Sha256::digest(owner_token).to_vec();
+
+        self.entries.write().unwrap().insert(
+            key_id.to_string(),
+            VaultEntry {
+                nonce: nonce_bytes.to_vec(),
+                ciphertext,
+                owner_token_hash,
+            },
+        );
+        Ok(hex::encode(owner_token))
+    }
+
+    /// Verify `owner_token` (hex) proves control of `key_id`, i.e. the
+    /// caller is the party that registered it, before signing on its behalf.
+    pub fn verify_owner_token(&self, key_id: &str, owner_token: &str) -> Result<(), Error> {
+        let entry = self
+            .entries
+            .read()
+            .unwrap()
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| Error::ParamError(format!("no vaulted key for {}", key_id)))?;
+        let presented = hex::decode(owner_token).map_err(|e| Error::ParamError(e.to_string()))?;
+        let presented_hash = Sha256::digest(presented);
+        if !constant_time_eq(&presented_hash, &entry.owner_token_hash) {
+            return Err(Error::ParamError("invalid owner token for key_id".into()));
+        }
+        Ok(())
+    }
+
+    pub fn persist(&self, vault_path: &str) -> Result<(), Error> {
+        let entries = self.entries.read().unwrap();
+        let raw = serde_json::to_string(&*entries)?;
+        std::fs::write(vault_path, raw).map_err(|e| Error::ParamError(e.to_string()))
+    }
+
+    /// Decrypt the secret key for `key_id`, pass it to `f`, then zeroize the
+    /// decrypted bytes before returning.
+    pub fn use_secret_key<T>(&self, key_id: &str, f: impl FnOnce(&SecretKey) -> T) -> Result<T, Error> {
+        let entry = self
+            .entries
+            .read()
+            .unwrap()
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| Error::ParamError(format!("no vaulted key for {}", key_id)))?;
+
+        let nonce = Nonce::from_slice(&entry.nonce);
+        let mut plaintext = self
+            .cipher
+            .decrypt(nonce, entry.ciphertext.as_ref())
+            .map_err(|e| Error::ParamError(e.to_string()))?;
+
+        let mut secret_key = SecretKey::parse_slice(&plaintext).map_err(|e| Error::ParamError(e.to_string()))?;
+        let result = f(&secret_key);
+        plaintext.zeroize();
+        // `libsecp256k1::SecretKey` doesn't implement `Zeroize` and holds no
+        // `Drop` impl of its own (it's a plain fixed-size scalar), so the
+        // copy `parse_slice` made above survives `f` returning unless we
+        // scrub it ourselves: overwrite its backing bytes in place before it
+        // goes out of scope.
+        unsafe {
+            let key_bytes = &mut secret_key as *mut SecretKey as *mut u8;
+            std::ptr::write_bytes(key_bytes, 0, std::mem::size_of::<SecretKey>());
+        }
+        Ok(result)
+    }
+}